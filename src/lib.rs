@@ -16,43 +16,399 @@
 extern crate lodestone_core;
 extern crate lodestone_point;
 
+use std::fmt;
+use std::str::FromStr;
+
 use lodestone_point::FeaturePoint;
 use lodestone_core::{utils, wgs84};
 
-pub extern fn destination(
-    point: &FeaturePoint, 
+/// Unit of measurement for a distance, as a typed alternative to the
+/// `units: &str` strings this crate has historically accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+  Degrees,
+  Kilometers,
+  Meters,
+  Miles,
+  Radians,
+}
+
+impl FromStr for Unit {
+  type Err = DestinationError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "degrees" => Ok(Unit::Degrees),
+      "kilometers" | "km" => Ok(Unit::Kilometers),
+      "meters" | "m" => Ok(Unit::Meters),
+      "miles" | "mi" => Ok(Unit::Miles),
+      "radians" => Ok(Unit::Radians),
+      _ => Err(DestinationError::UnknownUnit(s.to_string()))
+    }
+  }
+}
+
+/// Errors returned by the `try_*` functions in this crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DestinationError {
+  UnknownUnit(String),
+  InvalidCoordinate(String),
+  ConvergenceFailure,
+}
+
+impl fmt::Display for DestinationError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      DestinationError::UnknownUnit(ref units) => write!(f, "Unknown unit of measurement: {}", units),
+      DestinationError::InvalidCoordinate(ref coord) => write!(f, "Invalid NMEA coordinate: {}", coord),
+      DestinationError::ConvergenceFailure =>
+        write!(f, "Vincenty's formula failed to converge (start/bearing/distance may be nearly antipodal)")
+    }
+  }
+}
+
+impl ::std::error::Error for DestinationError {}
+
+fn radius_for_unit(unit: Unit) -> f64 {
+  match unit {
+    Unit::Degrees => 1.0_f64.to_degrees(),
+    Unit::Kilometers => wgs84::RADIUS / 1000.0,
+    Unit::Meters => wgs84::RADIUS,
+    Unit::Miles => utils::km_to_mi(wgs84::RADIUS / 1000.0),
+    Unit::Radians => 1.0,
+  }
+}
+
+/// Calculates the destination point given a starting point, distance, and
+/// initial bearing, returning an error instead of panicking if `units` is
+/// not recognized.
+///
+/// # Arguments
+/// * `point` - FeaturePoint
+/// * `distance` - distance in (degrees | kilometers | meters | miles | radians)
+/// * `bearing` - initial bearing in degrees
+/// * `units` - unit of measurement for distance
+pub fn try_destination(
+    point: &FeaturePoint,
     distance: f64,
     bearing: f64,
-    units: &str) -> FeaturePoint {
+    units: &str) -> Result<FeaturePoint, DestinationError> {
+
+  let unit = units.parse::<Unit>()?;
 
   let coord = point.coordinates();
   let lat = coord[1].to_radians();
   let lng = coord[0].to_radians();
   let bearing_rad = bearing.to_radians();
 
-  let radius = match units {
-    "degrees" => 1.0_f64.to_degrees(),
-    "kilometers" | "km" => wgs84::RADIUS / 1000.0,
-    "meters" | "m" => wgs84::RADIUS,
-    "miles" | "mi" => utils::km_to_mi(wgs84::RADIUS / 1000.0),
-    "radians" => 1.0,
-    _ => panic!("Unknown unit of measurement: {}", units)
-  };
+  let radius = radius_for_unit(unit);
 
   let dlat = (lat.sin() * (distance / radius).cos() +
               lat.cos() * (distance / radius).sin() * bearing_rad.cos()).asin();
-  let dlng = lng + 
+  let dlng = lng +
              (bearing_rad.sin() * (distance / radius).sin() * lat.cos()).atan2(
               (distance / radius).cos() - lat.sin() * dlat.sin()
              );
 
-  FeaturePoint::new(vec![dlng.to_degrees(), dlat.to_degrees()])
+  Ok(FeaturePoint::new(vec![dlng.to_degrees(), dlat.to_degrees()]))
+}
+
+pub extern fn destination(
+    point: &FeaturePoint,
+    distance: f64,
+    bearing: f64,
+    units: &str) -> FeaturePoint {
+
+  match try_destination(point, distance, bearing, units) {
+    Ok(point) => point,
+    Err(e) => panic!("{}", e)
+  }
+}
+
+/// Computes the rhumb-line (loxodrome) destination point: the point you
+/// reach by holding a constant compass bearing, rather than following the
+/// great-circle arc. Returns an error instead of panicking if `units` is
+/// not recognized.
+///
+/// # Arguments
+/// * `point` - FeaturePoint
+/// * `distance` - distance in (degrees | kilometers | meters | miles | radians)
+/// * `bearing` - constant bearing in degrees
+/// * `units` - unit of measurement for distance
+pub fn try_rhumb_destination(
+    point: &FeaturePoint,
+    distance: f64,
+    bearing: f64,
+    units: &str) -> Result<FeaturePoint, DestinationError> {
+
+  let unit = units.parse::<Unit>()?;
+
+  let coord = point.coordinates();
+  let lat1 = coord[1].to_radians();
+  let lng1 = coord[0].to_radians();
+  let bearing_rad = bearing.to_radians();
+
+  let radius = radius_for_unit(unit);
+  let delta = distance / radius;
+
+  let mut lat2 = lat1 + delta * bearing_rad.cos();
+
+  // Guard against the rhumb line crossing a pole.
+  if lat2.abs() > ::std::f64::consts::FRAC_PI_2 {
+    lat2 = if lat2 > 0.0 {
+      ::std::f64::consts::PI - lat2
+    } else {
+      -::std::f64::consts::PI - lat2
+    };
+  }
+
+  let dpsi = ((lat2 / 2.0 + ::std::f64::consts::FRAC_PI_4).tan() /
+              (lat1 / 2.0 + ::std::f64::consts::FRAC_PI_4).tan()).ln();
+
+  // East-west line: q by the limit of dlat/dpsi, since dpsi would be ~0.
+  let q = if dpsi.abs() > 1e-12 { (lat2 - lat1) / dpsi } else { lat1.cos() };
+
+  let dlng = delta * bearing_rad.sin() / q;
+  let mut lng2 = (lng1 + dlng).to_degrees();
+
+  // Normalize into (-180, 180].
+  lng2 %= 360.0;
+  if lng2 > 180.0 {
+    lng2 -= 360.0;
+  } else if lng2 <= -180.0 {
+    lng2 += 360.0;
+  }
+
+  Ok(FeaturePoint::new(vec![lng2, lat2.to_degrees()]))
+}
+
+pub extern fn rhumb_destination(
+    point: &FeaturePoint,
+    distance: f64,
+    bearing: f64,
+    units: &str) -> FeaturePoint {
+
+  match try_rhumb_destination(point, distance, bearing, units) {
+    Ok(point) => point,
+    Err(e) => panic!("{}", e)
+  }
+}
+
+// `wgs84::RADIUS` is 6378137.0, the WGS84 equatorial (semi-major) axis in
+// meters, not a mean spherical radius, so it's exactly the `a` Vincenty's
+// formula expects. Pair it with the standard WGS84 flattening for `b`.
+const WGS84_FLATTENING: f64 = 1.0 / 298.257223563;
+
+// Vincenty's iterative step in `try_geodesic_destination` converges in a handful of iterations for
+// ordinary inputs; this bounds the nearly-antipodal cases where it doesn't.
+const VINCENTY_MAX_ITERATIONS: u32 = 200;
+
+/// Solves the direct geodesic problem on the WGS84 ellipsoid using
+/// Vincenty's formula, which is accurate to sub-millimeter precision
+/// versus the spherical approximation used by `destination`. Returns an
+/// error instead of panicking if `units` isn't one Vincenty's formula
+/// works in, or if the solution fails to converge.
+///
+/// # Arguments
+/// * `point` - FeaturePoint
+/// * `distance` - distance in (kilometers | meters)
+/// * `bearing` - initial bearing in degrees
+/// * `units` - unit of measurement for distance
+pub fn try_geodesic_destination(
+    point: &FeaturePoint,
+    distance: f64,
+    bearing: f64,
+    units: &str) -> Result<FeaturePoint, DestinationError> {
+
+  let coord = point.coordinates();
+  let lat1 = coord[1].to_radians();
+  let lng1 = coord[0].to_radians();
+  let alpha1 = bearing.to_radians();
+
+  let a = wgs84::RADIUS;
+  let f = WGS84_FLATTENING;
+  let b = a * (1.0 - f);
+
+  let s = match units.parse::<Unit>()? {
+    Unit::Kilometers => distance * 1000.0,
+    Unit::Meters => distance,
+    _ => return Err(DestinationError::UnknownUnit(units.to_string()))
+  };
+
+  let tan_u1 = (1.0 - f) * lat1.tan();
+  let u1 = tan_u1.atan();
+  let sigma1 = (tan_u1).atan2(alpha1.cos());
+  let sin_alpha = u1.cos() * alpha1.sin();
+  let cos_sq_alpha = 1.0 - sin_alpha * sin_alpha;
+  let u_sq = cos_sq_alpha * (a * a - b * b) / (b * b);
+
+  let big_a = 1.0 + u_sq / 16384.0 *
+              (4096.0 + u_sq * (-768.0 + u_sq * (320.0 - 175.0 * u_sq)));
+  let big_b = u_sq / 1024.0 *
+              (256.0 + u_sq * (-128.0 + u_sq * (74.0 - 47.0 * u_sq)));
+
+  let mut sigma = s / (b * big_a);
+  let (sin_u1, cos_u1) = (u1.sin(), u1.cos());
+  let mut converged = false;
+
+  for _ in 0..VINCENTY_MAX_ITERATIONS {
+    let two_sigma_m = 2.0 * sigma1 + sigma;
+    let delta_sigma = big_b * sigma.sin() * (two_sigma_m.cos() +
+                       big_b / 4.0 * (sigma.cos() * (-1.0 + 2.0 * two_sigma_m.cos().powi(2)) -
+                       big_b / 6.0 * two_sigma_m.cos() * (-3.0 + 4.0 * sigma.sin().powi(2)) *
+                       (-3.0 + 4.0 * two_sigma_m.cos().powi(2))));
+
+    let sigma_prime = sigma;
+    sigma = s / (b * big_a) + delta_sigma;
+
+    if (sigma - sigma_prime).abs() < 1e-12 {
+      converged = true;
+      break;
+    }
+  }
+
+  if !converged {
+    return Err(DestinationError::ConvergenceFailure);
+  }
+
+  let two_sigma_m = 2.0 * sigma1 + sigma;
+  let lat2 = (sin_u1 * sigma.cos() + cos_u1 * sigma.sin() * alpha1.cos()).atan2(
+             (1.0 - f) * ((sin_alpha).powi(2) +
+             (sin_u1 * sigma.sin() - cos_u1 * sigma.cos() * alpha1.cos()).powi(2)).sqrt());
+
+  let lambda = (sigma.sin() * alpha1.sin()).atan2(
+               cos_u1 * sigma.cos() - sin_u1 * sigma.sin() * alpha1.cos());
+
+  let c = f / 16.0 * cos_sq_alpha * (4.0 + f * (4.0 - 3.0 * cos_sq_alpha));
+  let l = lambda - (1.0 - c) * f * sin_alpha * (sigma + c * sigma.sin() *
+          (two_sigma_m.cos() + c * sigma.cos() * (-1.0 + 2.0 * two_sigma_m.cos().powi(2))));
+
+  let lng2 = (lng1 + l).to_degrees();
+
+  Ok(FeaturePoint::new(vec![lng2, lat2.to_degrees()]))
+}
+
+pub extern fn geodesic_destination(
+    point: &FeaturePoint,
+    distance: f64,
+    bearing: f64,
+    units: &str) -> FeaturePoint {
+
+  match try_geodesic_destination(point, distance, bearing, units) {
+    Ok(point) => point,
+    Err(e) => panic!("{}", e)
+  }
+}
+
+/// Solves the inverse great-circle problem: given a start and end point,
+/// returns the initial bearing and distance that `destination` would need
+/// to reconstruct `end` from `start`. Returns an error instead of
+/// panicking if `units` is not recognized.
+///
+/// # Arguments
+/// * `start` - FeaturePoint
+/// * `end` - FeaturePoint
+/// * `units` - unit of measurement for the returned distance
+///
+/// # Returns
+/// `(bearing_degrees, distance)`
+pub fn try_inverse(
+    start: &FeaturePoint,
+    end: &FeaturePoint,
+    units: &str) -> Result<(f64, f64), DestinationError> {
+
+  let unit = units.parse::<Unit>()?;
+
+  let start_coord = start.coordinates();
+  let end_coord = end.coordinates();
+
+  let lat1 = start_coord[1].to_radians();
+  let lng1 = start_coord[0].to_radians();
+  let lat2 = end_coord[1].to_radians();
+  let lng2 = end_coord[0].to_radians();
+
+  let dlat = lat2 - lat1;
+  let dlng = lng2 - lng1;
+
+  let bearing_rad = dlng.sin() * lat2.cos();
+  let bearing = bearing_rad.atan2(
+    lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlng.cos()
+  ).to_degrees();
+
+  let a = (dlat / 2.0).sin().powi(2) +
+          lat1.cos() * lat2.cos() * (dlng / 2.0).sin().powi(2);
+  let c = 2.0 * a.sqrt().atan2((1.0 - a).sqrt());
+
+  let radius = radius_for_unit(unit);
+  let distance = radius * c;
+
+  Ok(((bearing + 360.0) % 360.0, distance))
+}
+
+pub extern fn inverse(
+    start: &FeaturePoint,
+    end: &FeaturePoint,
+    units: &str) -> (f64, f64) {
+
+  match try_inverse(start, end, units) {
+    Ok(result) => result,
+    Err(e) => panic!("{}", e)
+  }
+}
+
+// Parses an NMEA degrees-decimal-minutes coordinate, e.g. "3953.4210"/"N",
+// into signed decimal degrees.
+fn parse_nmea_coord(value: &str, direction: &str) -> Result<f64, DestinationError> {
+  let raw: f64 = value.parse()
+    .map_err(|_| DestinationError::InvalidCoordinate(value.to_string()))?;
+
+  let degrees = (raw / 100.0).floor();
+  let minutes = raw - degrees * 100.0;
+  let decimal = degrees + minutes / 60.0;
+
+  match direction {
+    "N" | "E" => Ok(decimal),
+    "S" | "W" => Ok(-decimal),
+    _ => Err(DestinationError::InvalidCoordinate(direction.to_string()))
+  }
+}
+
+/// Builds a destination point from a start position given in NMEA
+/// degrees-decimal-minutes form (e.g. `"3953.4210", "N"`), as emitted by
+/// many GPS receivers, instead of signed decimal degrees.
+///
+/// # Arguments
+/// * `lat` - latitude in NMEA ddmm.mmmm form
+/// * `lat_dir` - "N" or "S"
+/// * `lon` - longitude in NMEA dddmm.mmmm form
+/// * `lon_dir` - "E" or "W"
+/// * `distance` - distance in (degrees | kilometers | meters | miles | radians)
+/// * `bearing` - initial bearing in degrees
+/// * `units` - unit of measurement for distance
+pub fn destination_from_nmea(
+    lat: &str,
+    lat_dir: &str,
+    lon: &str,
+    lon_dir: &str,
+    distance: f64,
+    bearing: f64,
+    units: &str) -> Result<FeaturePoint, DestinationError> {
+
+  let lat_deg = parse_nmea_coord(lat, lat_dir)?;
+  let lng_deg = parse_nmea_coord(lon, lon_dir)?;
+  let point = FeaturePoint::new(vec![lng_deg, lat_deg]);
+
+  try_destination(&point, distance, bearing, units)
 }
 
 #[cfg(test)]
 mod tests {
   use lodestone_point::FeaturePoint;
-  use super::destination;
+  use super::{destination, destination_from_nmea, geodesic_destination, inverse, rhumb_destination, try_destination, DestinationError};
+
+  fn assert_approx_eq(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < 1e-6, "{} != {}", actual, expected);
+  }
 
   #[test]
   #[should_panic(expected = "Unknown unit of measurement")]
@@ -62,6 +418,14 @@ mod tests {
     destination(&sf_point, 100.0, 50.0, "leagues");
   }
 
+  #[test]
+  fn test_try_destination_wrong_units() {
+    let sf = vec![-122.4167,37.7833];
+    let sf_point = FeaturePoint::new(sf);
+    let result = try_destination(&sf_point, 100.0, 50.0, "leagues");
+    assert_eq!(result, Err(DestinationError::UnknownUnit("leagues".to_string())));
+  }
+
   #[test]
   fn test_simple() {
     let pt1 = FeaturePoint::new(vec![0.0, 0.0]);
@@ -108,4 +472,64 @@ mod tests {
 
     assert_eq!(dest, ny_point);
   }
+
+  #[test]
+  fn test_rhumb_destination_from_sf_using_kilometers() {
+    let sf = vec![-122.4167,37.7833];
+    let sf_point = FeaturePoint::new(sf);
+    let distance = 4185.03442485938; // rhumb-line distance to ny in km
+    let bearing = 85.53096307224969; // constant rhumb bearing to ny
+
+    // expected
+    let ny = vec![-74.0059,40.7127];
+    let ny_point = FeaturePoint::new(ny);
+
+    let dest = rhumb_destination(&sf_point, distance, bearing, "km");
+
+    assert_eq!(dest, ny_point);
+  }
+
+  #[test]
+  fn test_geodesic_destination_from_sf_using_kilometers() {
+    let sf = vec![-122.4167,37.7833];
+    let sf_point = FeaturePoint::new(sf);
+    let distance = 4133.177968880825; // great-circle distance to ny in km
+    let bearing = 69.91944547551958; // great-circle bearing to ny
+
+    // expected: the ellipsoid solution for the same bearing/distance lands
+    // close to, but not exactly on, the spherical great-circle target.
+    let expected = vec![-74.0664455, 40.7316188];
+    let expected_point = FeaturePoint::new(expected);
+
+    let dest = geodesic_destination(&sf_point, distance, bearing, "km");
+
+    assert_eq!(dest, expected_point);
+  }
+
+  #[test]
+  fn test_inverse_from_sf_to_ny_using_kilometers() {
+    let sf = FeaturePoint::new(vec![-122.4167,37.7833]);
+    let ny = FeaturePoint::new(vec![-74.0059,40.7127]);
+
+    // these are the same bearing/distance hard-coded as fixtures in the
+    // test_from_sf_using_* tests above; `inverse` should derive them back.
+    let (bearing, distance) = inverse(&sf, &ny, "km");
+
+    assert_approx_eq(bearing, 69.91944547551958);
+    assert_approx_eq(distance, 4133.177968880825);
+  }
+
+  #[test]
+  fn test_destination_from_nmea() {
+    let expected = FeaturePoint::new(vec![-74.0059, 40.7127]);
+
+    let dest = destination_from_nmea("4042.7620", "N", "07400.3540", "W", 0.0, 0.0, "km").unwrap();
+    assert_eq!(dest, expected);
+  }
+
+  #[test]
+  fn test_destination_from_nmea_invalid_direction() {
+    let result = destination_from_nmea("3953.4210", "X", "12212.5000", "W", 0.0, 0.0, "km");
+    assert_eq!(result, Err(DestinationError::InvalidCoordinate("X".to_string())));
+  }
 }